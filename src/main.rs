@@ -1,7 +1,7 @@
-use std::{io::{BufReader, BufRead}, fs::File, collections::{VecDeque, HashMap}};
+use std::{io::Write, collections::{VecDeque, HashMap, BinaryHeap}, cmp::Reverse, thread, time::Duration};
 
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 enum Amphipod {
 
     Amber,
@@ -12,13 +12,11 @@ enum Amphipod {
 
 impl Amphipod {
 
-    fn get_destination_cup_index(&self) -> usize {
-        match self {
-            Self::Amber => 0,
-            Self::Bronze => 1,
-            Self::Copper => 2,
-            Self::Desert => 3
-        }
+    // the species-to-room assignment is board layout, not an intrinsic
+    // property of the species, so it is looked up in the board's own map
+    // rather than hardcoded here
+    fn get_destination_cup_index(&self, destinations: &HashMap<Amphipod, usize>) -> usize {
+        *destinations.get(self).expect("amphipod has no configured destination room")
     }
 
     fn get_cost_per_move(&self) -> u32 {
@@ -26,11 +24,37 @@ impl Amphipod {
             Self::Amber => 1,
             Self::Bronze => 10,
             Self::Copper => 100,
-            Self::Desert => 1000,   
+            Self::Desert => 1000,
+        }
+    }
+
+    fn get_label(&self) -> char {
+        match self {
+            Self::Amber => 'A',
+            Self::Bronze => 'B',
+            Self::Copper => 'C',
+            Self::Desert => 'D',
+        }
+    }
+
+    // ANSI colour so each species stays visually distinguishable in the
+    // terminal animation
+    fn get_color_code(&self) -> &'static str {
+        match self {
+            Self::Amber => "\x1b[93m",
+            Self::Bronze => "\x1b[33m",
+            Self::Copper => "\x1b[36m",
+            Self::Desert => "\x1b[35m",
         }
     }
 }
 
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+// fixed left-to-right column order every board variant assigns rooms in
+const SPECIES_ORDER: [Amphipod; 4] = [Amphipod::Amber, Amphipod::Bronze, Amphipod::Copper, Amphipod::Desert];
+
 impl TryFrom<char> for Amphipod {
     type Error = ();
 
@@ -45,19 +69,33 @@ impl TryFrom<char> for Amphipod {
     }
 }
 
-#[derive(Clone)]
-struct Room {
+// the mutable board state: how many amphipods of which species sit in the
+// hallway placeholders and the room stacks (each stack carries its room's
+// depth). Hallway and room-entrance positions and the species-to-room map
+// live in `BoardLayout` instead, so that this struct stays cheap to
+// hash/clone for search-state memoization
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Board {
 
-    placeholders: [Option<Amphipod>; 7],
-    cups: [Cup; 4]
+    placeholders: Vec<Option<Amphipod>>,
+    cups: Vec<Cup>
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Cup {
     capacity: usize,
     content: VecDeque<Amphipod>
 }
 
+// a single recorded step of the winning path, kept around so the result is
+// auditable instead of just a bare cost
+#[derive(Clone, Debug)]
+enum Move {
+    CupToHall { cup: usize, placeholder: usize, energy: u32 },
+    HallToCup { placeholder: usize, cup: usize, energy: u32 },
+    CupToCup { from: usize, to: usize, energy: u32 }
+}
+
 impl Cup {
 
     fn new(capacity: usize) -> Self {
@@ -73,50 +111,80 @@ impl Cup {
     }
 }
 
+// the static geometry of a board: how many rooms it has and where the
+// hallway stops and room entrances sit, plus which species belongs in
+// which room. Computed once at parse time and threaded by reference
+// through the search, since it never changes once a board is built
+struct BoardLayout {
+    placeholder_positions: Vec<u32>,
+    cup_positions: Vec<u32>,
+    destinations: HashMap<Amphipod, usize>
+}
 
-impl Room {
+impl BoardLayout {
 
-    const PLACEHOLDER_POSITIONS: [u32; 7] = [0, 1, 3, 5, 7, 9, 10];
-    const CUP_POSITIONS: [u32; 4] = [2, 4, 6, 8];
+    fn new(room_count: usize) -> Self {
 
+        let cup_positions: Vec<u32> = (0..room_count).map(|i| 2 * (i as u32 + 1)).collect();
 
-    fn is_ordered(&self) -> bool {
+        let mut placeholder_positions = vec![0, 1];
+        placeholder_positions.extend(cup_positions[..room_count - 1].iter().map(|p| p + 1));
+        placeholder_positions.push(cup_positions[room_count - 1] + 1);
+        placeholder_positions.push(cup_positions[room_count - 1] + 2);
+
+        let destinations = SPECIES_ORDER.iter().take(room_count).enumerate()
+            .map(|(index, &amphipod)| (amphipod, index))
+            .collect();
+
+        Self{placeholder_positions, cup_positions, destinations}
+    }
+}
+
+impl Board {
+
+    fn is_ordered(&self, layout: &BoardLayout) -> bool {
 
         for (index, cup) in self.cups.iter().enumerate() {
-            if cup.content.len() != cup.capacity || cup.content.iter().any(|a| a.get_destination_cup_index() != index) {
+            if cup.content.len() != cup.capacity || cup.content.iter().any(|a| a.get_destination_cup_index(&layout.destinations) != index) {
                 return false;
             }
         }
         return true;
     }
 
-    fn move_amphipod_to_placeholder(&mut self, origin_cup_index: usize, placeholder: usize) -> u32 {
+    fn move_amphipod_to_placeholder(&mut self, origin_cup_index: usize, placeholder: usize, layout: &BoardLayout, log: &mut Vec<Move>) -> u32 {
 
         let origin_cup = self.cups.get_mut(origin_cup_index).unwrap();
 
         let amphipod = origin_cup.content.pop_front().unwrap();
         self.placeholders[placeholder] = Some(amphipod);
 
-        let horizontal_distance = Self::PLACEHOLDER_POSITIONS[placeholder].abs_diff(Self::CUP_POSITIONS[origin_cup_index]);
+        let horizontal_distance = layout.placeholder_positions[placeholder].abs_diff(layout.cup_positions[origin_cup_index]);
         let vertical_distance = origin_cup.capacity - origin_cup.content.len();
         let distance =  vertical_distance as u32 + horizontal_distance;
-        return distance * amphipod.get_cost_per_move();
+        let energy = distance * amphipod.get_cost_per_move();
+
+        log.push(Move::CupToHall{cup: origin_cup_index, placeholder, energy});
+        return energy;
     }
 
-    fn move_amphipod_from_placeholder_to_destination(&mut self, placeholder: usize, destination_cup_index: usize) -> u32 {
+    fn move_amphipod_from_placeholder_to_destination(&mut self, placeholder: usize, destination_cup_index: usize, layout: &BoardLayout, log: &mut Vec<Move>) -> u32 {
 
         let destination_cup = self.cups.get_mut(destination_cup_index).unwrap();
 
         let amphipod = self.placeholders[placeholder].take().unwrap();
         destination_cup.content.push_front(amphipod);
 
-        let horizontal_distance = Self::PLACEHOLDER_POSITIONS[placeholder].abs_diff(Self::CUP_POSITIONS[destination_cup_index]);
+        let horizontal_distance = layout.placeholder_positions[placeholder].abs_diff(layout.cup_positions[destination_cup_index]);
         let vertical_distance = 1 + destination_cup.capacity - destination_cup.content.len();
         let distance =  vertical_distance as u32 + horizontal_distance;
-        return distance * amphipod.get_cost_per_move();
+        let energy = distance * amphipod.get_cost_per_move();
+
+        log.push(Move::HallToCup{placeholder, cup: destination_cup_index, energy});
+        return energy;
     }
 
-    fn move_amphipod_from_origin_to_destination(&mut self, origin_cup_index: usize, destination_cup_index: usize) -> u32 {
+    fn move_amphipod_from_origin_to_destination(&mut self, origin_cup_index: usize, destination_cup_index: usize, layout: &BoardLayout, log: &mut Vec<Move>) -> u32 {
 
         let origin_cup = self.cups.get_mut(origin_cup_index).unwrap();
         let amphipod = origin_cup.content.pop_front().unwrap();
@@ -127,10 +195,13 @@ impl Room {
         let destination_cup = self.cups.get(destination_cup_index).unwrap();
         let origin_cup =  self.cups.get(origin_cup_index).unwrap();
 
-        let horizontal_distance = Self::CUP_POSITIONS[origin_cup_index].abs_diff(Self::CUP_POSITIONS[destination_cup_index]);
+        let horizontal_distance = layout.cup_positions[origin_cup_index].abs_diff(layout.cup_positions[destination_cup_index]);
         let vertical_distance = origin_cup.capacity - origin_cup.content.len() + destination_cup.capacity - destination_cup.content.len() + 1;
         let distance =  vertical_distance as u32 + horizontal_distance;
-        return distance * amphipod.get_cost_per_move();
+        let energy = distance * amphipod.get_cost_per_move();
+
+        log.push(Move::CupToCup{from: origin_cup_index, to: destination_cup_index, energy});
+        return energy;
     }
 
     fn get_available_placeholders(&self, cup_number: usize) -> Vec<usize> {
@@ -157,13 +228,13 @@ impl Room {
 
         res
     }
-    
-    fn check_destination(&self, destination_cup_index: usize) -> bool {
+
+    fn check_destination(&self, destination_cup_index: usize, layout: &BoardLayout) -> bool {
 
         let cup = &self.cups[destination_cup_index];
 
         return !cup.is_full() && cup.content.iter().all(
-            |amphipod| amphipod.get_destination_cup_index()==destination_cup_index);
+            |amphipod| amphipod.get_destination_cup_index(&layout.destinations)==destination_cup_index);
     }
 
     fn check_path_placeholder_destination(&self, placeholder: usize, destination_cup: usize) -> bool {
@@ -172,7 +243,7 @@ impl Room {
             for placeholder_to_check in placeholder + 1..destination_cup + 2 {
                 if self.placeholders[placeholder_to_check] == None {
                     continue;
-                } 
+                }
                 return false;
             }
             return true;
@@ -180,7 +251,7 @@ impl Room {
             for placeholder_to_check in destination_cup + 2..placeholder {
                 if self.placeholders[placeholder_to_check] == None {
                     continue;
-                } 
+                }
                 return false;
             }
             return true;
@@ -194,14 +265,14 @@ impl Room {
             for placeholder_to_check in origin_cup + 2..destination_cup + 2 {
                 if self.placeholders[placeholder_to_check] != None {
                     return false;
-                } 
+                }
             }
             return true;
         } else if origin_cup > destination_cup {
             for placeholder_to_check in destination_cup + 2..origin_cup + 2 {
                 if self.placeholders[placeholder_to_check] != None {
                     return false;
-                } 
+                }
             }
             return true;
         }
@@ -210,31 +281,121 @@ impl Room {
 
 }
 
+fn render_slot(slot: &Option<Amphipod>) -> String {
+    match slot {
+        Some(amphipod) => format!("{}{}{}", amphipod.get_color_code(), amphipod.get_label(), ANSI_RESET),
+        None => ".".to_string()
+    }
+}
+
+// the hallway is `2 * room_count + 3` cells wide (the widest row in the
+// diagram); walls and the hallway row are all sized off that instead of
+// literal AoC-part-1 constants, so boards of any room count stay aligned
+fn render_board(board: &Board, layout: &BoardLayout) -> String {
+
+    let room_count = board.cups.len();
+    let hallway_width = 2 * room_count + 3;
+
+    let mut out = String::new();
+
+    out.push_str(&"#".repeat(hallway_width + 2));
+    out.push_str("\n#");
+    for x in 0..hallway_width as u32 {
+        match layout.placeholder_positions.iter().position(|&position| position == x) {
+            Some(placeholder_index) => out.push_str(&render_slot(&board.placeholders[placeholder_index])),
+            None => out.push('.')
+        }
+    }
+    out.push_str("#\n");
+
+    let depth = board.cups[0].capacity;
+    for row in 0..depth {
+        out.push_str(if row == 0 { "###" } else { "  #" });
+        for cup in board.cups.iter() {
+            out.push_str(&render_slot(&cup.content.get(row).copied()));
+            out.push('#');
+        }
+        out.push_str(if row == 0 { "##\n" } else { "\n" });
+    }
+    out.push_str("  ");
+    out.push_str(&"#".repeat(hallway_width - 2));
+    out.push('\n');
+
+    out
+}
+
+// replays a single recorded move onto a board, returning the energy it
+// cost (the same value already stored in the `Move`)
+fn apply_move(board: &mut Board, layout: &BoardLayout, mv: &Move) -> u32 {
+
+    let mut discard = Vec::new();
+
+    match *mv {
+        Move::CupToHall{cup, placeholder, energy} => {
+            board.move_amphipod_to_placeholder(cup, placeholder, layout, &mut discard);
+            energy
+        },
+        Move::HallToCup{placeholder, cup, energy} => {
+            board.move_amphipod_from_placeholder_to_destination(placeholder, cup, layout, &mut discard);
+            energy
+        },
+        Move::CupToCup{from, to, energy} => {
+            board.move_amphipod_from_origin_to_destination(from, to, layout, &mut discard);
+            energy
+        }
+    }
+}
+
+// steps through the winning path frame by frame, redrawing the board after
+// each move with a short pause, in the style of a termion-driven AoC replay
+fn animate_solution(init_board: &Board, layout: &BoardLayout, moves: &[Move]) {
+
+    let mut board = init_board.clone();
+    let mut cost = 0u32;
+    let stdout = std::io::stdout();
+
+    let print_frame = |board: &Board, cost: u32| {
+        let mut handle = stdout.lock();
+        write!(handle, "{}{}\nEnergy so far: {}\n", ANSI_CLEAR_SCREEN, render_board(board, layout), cost).unwrap();
+        handle.flush().unwrap();
+    };
+
+    print_frame(&board, cost);
+    thread::sleep(Duration::from_millis(500));
+
+    for mv in moves {
+        cost += apply_move(&mut board, layout, mv);
+        print_frame(&board, cost);
+        thread::sleep(Duration::from_millis(400));
+    }
+}
+
 #[derive(Clone)]
 struct WalkState {
-    room: Room,
-    cost: u32
+    board: Board,
+    cost: u32,
+    moves: Vec<Move>
 }
 
 impl WalkState {
 
-    fn get_next_states(&self) -> Vec<WalkState> {
+    fn get_next_states(&self, layout: &BoardLayout) -> Vec<WalkState> {
 
         let mut res = Vec::new();
 
-        for (cup_index, cup) in self.room.cups.iter().enumerate() {
+        for (cup_index, cup) in self.board.cups.iter().enumerate() {
 
-            if cup.content.iter().all(|a| a.get_destination_cup_index()==cup_index) {
+            if cup.content.iter().all(|a| a.get_destination_cup_index(&layout.destinations)==cup_index) {
                 continue;
             }
 
-            let available_placeholders = self.room.get_available_placeholders(cup_index);
+            let available_placeholders = self.board.get_available_placeholders(cup_index);
 
             for available_placeholder in available_placeholders {
                 let mut new_state = self.clone();
 
-                new_state.cost += new_state.room.move_amphipod_to_placeholder(
-                    cup_index, available_placeholder);
+                new_state.cost += new_state.board.move_amphipod_to_placeholder(
+                    cup_index, available_placeholder, layout, &mut new_state.moves);
                 res.push(new_state);
             }
         }
@@ -242,32 +403,34 @@ impl WalkState {
         return res;
     }
 
-    fn progress(&mut self) {
-        while self.try_progress() {}
+    fn progress(&mut self, layout: &BoardLayout) {
+        while self.try_progress(layout) {}
     }
 
-    fn try_progress(&mut self) -> bool {
+    fn try_progress(&mut self, layout: &BoardLayout) -> bool {
 
         let mut res = false;
 
-        for cup_index in 0..self.room.cups.len() {
-            if let Some(amphipod) = self.room.cups[cup_index].content.get(0) {
-                if cup_index != amphipod.get_destination_cup_index()
-                && self.room.check_destination(amphipod.get_destination_cup_index())
-                && self.room.check_path_origin_destination(cup_index, amphipod.get_destination_cup_index()) {
-                    self.cost += self.room.move_amphipod_from_origin_to_destination(
-                        cup_index, amphipod.get_destination_cup_index());
+        for cup_index in 0..self.board.cups.len() {
+            if let Some(amphipod) = self.board.cups[cup_index].content.get(0) {
+                let destination_cup_index = amphipod.get_destination_cup_index(&layout.destinations);
+                if cup_index != destination_cup_index
+                && self.board.check_destination(destination_cup_index, layout)
+                && self.board.check_path_origin_destination(cup_index, destination_cup_index) {
+                    self.cost += self.board.move_amphipod_from_origin_to_destination(
+                        cup_index, destination_cup_index, layout, &mut self.moves);
                         res = true;
                 }
             }
         }
 
-        for placeholder_index in 0..self.room.placeholders.len() {
-            if let Some(amphipod) = self.room.placeholders[placeholder_index] {
-                if self.room.check_destination(amphipod.get_destination_cup_index())
-                && self.room.check_path_placeholder_destination(placeholder_index, amphipod.get_destination_cup_index()) {
-                    self.cost += self.room.move_amphipod_from_placeholder_to_destination(
-                        placeholder_index, amphipod.get_destination_cup_index());
+        for placeholder_index in 0..self.board.placeholders.len() {
+            if let Some(amphipod) = self.board.placeholders[placeholder_index] {
+                let destination_cup_index = amphipod.get_destination_cup_index(&layout.destinations);
+                if self.board.check_destination(destination_cup_index, layout)
+                && self.board.check_path_placeholder_destination(placeholder_index, destination_cup_index) {
+                    self.cost += self.board.move_amphipod_from_placeholder_to_destination(
+                        placeholder_index, destination_cup_index, layout, &mut self.moves);
                         res = true;
                 }
             }
@@ -276,23 +439,25 @@ impl WalkState {
         res
     }
 
-    fn project_costs(&self) -> u32 {
+    fn project_costs(&self, layout: &BoardLayout) -> u32 {
 
         let mut res = self.cost;
 
-        for (placeholder_index, placeholder) in self.room.placeholders.iter().enumerate() {
+        for (placeholder_index, placeholder) in self.board.placeholders.iter().enumerate() {
             if let Some(amphipod) = placeholder {
-                let length = 1 + Room::PLACEHOLDER_POSITIONS[placeholder_index].abs_diff(
-                    Room::CUP_POSITIONS[amphipod.get_destination_cup_index()]);
+                let destination_cup_index = amphipod.get_destination_cup_index(&layout.destinations);
+                let length = 1 + layout.placeholder_positions[placeholder_index].abs_diff(
+                    layout.cup_positions[destination_cup_index]);
                 res += length * amphipod.get_cost_per_move();
             }
         }
 
-        for (cup_index, cup) in self.room.cups.iter().enumerate() {
+        for (cup_index, cup) in self.board.cups.iter().enumerate() {
             for amphipod in cup.content.iter() {
-                if cup_index != amphipod.get_destination_cup_index() {
-                    let length = 2 + Room::CUP_POSITIONS[cup_index].abs_diff(
-                        Room::CUP_POSITIONS[amphipod.get_destination_cup_index()]);
+                let destination_cup_index = amphipod.get_destination_cup_index(&layout.destinations);
+                if cup_index != destination_cup_index {
+                    let length = 2 + layout.cup_positions[cup_index].abs_diff(
+                        layout.cup_positions[destination_cup_index]);
                     res += length * amphipod.get_cost_per_move();
                 }
             }
@@ -301,56 +466,179 @@ impl WalkState {
         res
     }
 
+    // admissible remaining-cost estimate: the pure per-amphipod lower bound
+    // minus the cost already spent getting here
+    fn heuristic(&self, layout: &BoardLayout) -> u32 {
+        self.project_costs(layout) - self.cost
+    }
+
+}
+
+// only `cost` participates in ordering; it is merely a tie-breaker for the
+// BinaryHeap (which is keyed primarily on `f = cost + heuristic`), not a
+// notion of state identity
+impl PartialEq for WalkState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
 }
 
-fn simulate_ordering(init_room: &Room) -> u32 {
-   
-    let init_state = WalkState{room: init_room.clone(), cost: 0};
+impl Eq for WalkState {}
 
-    let mut stack: VecDeque<WalkState> = VecDeque::new();
-    stack.push_back(init_state);
+impl PartialOrd for WalkState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    let mut min_score = u32::MAX;
+impl Ord for WalkState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.cost.cmp(&other.cost)
+    }
+}
 
-    while let Some(current_state) = stack.pop_back() {
-        
-        if min_score < current_state.cost {
-            continue;
-        } else if min_score < current_state.project_costs() {
-            continue;
+fn simulate_ordering(init_board: &Board, layout: &BoardLayout) -> (u32, Vec<Move>) {
+
+    let init_state = WalkState{board: init_board.clone(), cost: 0, moves: Vec::new()};
+
+    let mut heap: BinaryHeap<Reverse<(u32, WalkState)>> = BinaryHeap::new();
+    heap.push(Reverse((init_state.heuristic(layout) + init_state.cost, init_state)));
+
+    // canonical board -> cheapest cost it has been dequeued at, so a state
+    // reached again at an equal or higher cost is pruned outright
+    let mut best_cost: HashMap<Board, u32> = HashMap::new();
+
+    while let Some(Reverse((f, current_state))) = heap.pop() {
+
+        if let Some(&known_cost) = best_cost.get(&current_state.board) {
+            if known_cost <= current_state.cost {
+                continue;
+            }
         }
+        best_cost.insert(current_state.board.clone(), current_state.cost);
 
         let mut intermediate_state = current_state.clone();
-        intermediate_state.progress();
-
-        if intermediate_state.room.is_ordered() {
-            min_score = std::cmp::min(min_score, intermediate_state.cost);
-            println!("Found solution. Cost: {}", min_score);
+        intermediate_state.progress(layout);
+
+        if intermediate_state.board.is_ordered(layout) {
+            // the forced moves made by progress() can cost more than the
+            // heuristic estimated, so a finished board is queued again at its
+            // real cost; once it comes off the heap with nothing cheaper
+            // left, A* guarantees it is optimal
+            if intermediate_state.cost <= f {
+                return (intermediate_state.cost, intermediate_state.moves);
+            }
+            heap.push(Reverse((intermediate_state.cost, intermediate_state)));
             continue;
         }
-        
-        intermediate_state.get_next_states().iter().for_each(|s| stack.push_back(s.clone()));
+
+        for next_state in intermediate_state.get_next_states(layout) {
+            let f = next_state.cost + next_state.heuristic(layout);
+            heap.push(Reverse((f, next_state)));
+        }
     }
 
-    min_score
+    (u32::MAX, Vec::new())
 }
 
 
-fn main() {
+#[derive(Debug)]
+enum ParseError {
+    TooFewLines,
+    MissingHallway,
+    NoRoomColumns,
+    RaggedRoom{column: usize},
+    InvalidOccupant{found: char},
+    UnsupportedSpecies{found: char, room_count: usize}
+}
 
-    let lines: Vec<String> = BufReader::new(File::open("input.txt").unwrap()).lines().map(|l| l.unwrap()).collect();
-    let depth = lines.len() - 3;
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::TooFewLines => write!(f, "input has too few lines to contain a board"),
+            Self::MissingHallway => write!(f, "the two hallway border rows are missing or malformed"),
+            Self::NoRoomColumns => write!(f, "could not find a supported number of room columns (expected 1-4)"),
+            Self::RaggedRoom{column} => write!(f, "room column {} is ragged (missing a row)", column),
+            Self::InvalidOccupant{found} => write!(f, "expected one of A/B/C/D, found '{}'", found),
+            Self::UnsupportedSpecies{found, room_count} => write!(f, "'{}' has no destination room on a {}-room board", found, room_count)
+        }
+    }
+}
+
+// a room column is a single non-'#' character framed by '#' on both sides;
+// this locates *where* the rooms are independent of what currently occupies
+// them, so a garbled occupant is reported rather than silently dropping the column
+fn find_room_columns(line: &str) -> Vec<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    (1..chars.len().saturating_sub(1))
+        .filter(|&index| chars[index] != '#' && chars[index - 1] == '#' && chars[index + 1] == '#')
+        .collect()
+}
+
+// scans the diagram structurally instead of indexing fixed offsets, so it
+// tolerates CRLF input, reports what is wrong rather than panicking, and
+// infers the board's geometry instead of assuming the standard 4 rooms
+fn parse_board(input: &str) -> Result<(Board, BoardLayout), ParseError> {
+
+    let lines: Vec<&str> = input.lines().map(|line| line.trim_end_matches('\r')).collect();
+
+    if lines.len() < 3 {
+        return Err(ParseError::TooFewLines);
+    }
+
+    if !lines[0].starts_with('#') || !lines[1].starts_with('#') {
+        return Err(ParseError::MissingHallway);
+    }
+
+    let first_room_row = (2..lines.len()).find(|&index| !find_room_columns(lines[index]).is_empty())
+        .ok_or(ParseError::NoRoomColumns)?;
+
+    let columns = find_room_columns(lines[first_room_row]);
+
+    if columns.is_empty() || columns.len() > SPECIES_ORDER.len() {
+        return Err(ParseError::NoRoomColumns);
+    }
+
+    let room_rows: Vec<&str> = lines[first_room_row..].iter()
+        .take_while(|line| !line.chars().all(|c| c == '#' || c == ' '))
+        .copied()
+        .collect();
+
+    let depth = room_rows.len();
+    let room_count = columns.len();
+    let layout = BoardLayout::new(room_count);
 
     let mut cups = Vec::new();
+    for &column in &columns {
+        let mut cup = Cup::new(depth);
+        for row in &room_rows {
+            let occupant = row.chars().nth(column).ok_or(ParseError::RaggedRoom{column})?;
+            let amphipod = Amphipod::try_from(occupant).map_err(|_| ParseError::InvalidOccupant{found: occupant})?;
+            if !layout.destinations.contains_key(&amphipod) {
+                return Err(ParseError::UnsupportedSpecies{found: occupant, room_count});
+            }
+            cup.content.push_back(amphipod);
+        }
+        cups.push(cup);
+    }
 
-    (0..4).for_each(|cup_index| {
-        cups.push((0..depth).fold(Cup::new(depth), |mut cup, d| {
-            cup.content.push_back(lines[2 + d].chars().nth(cup_index * 2 + 3).unwrap().try_into().unwrap());
-            cup
-        }))
+    let board = Board { placeholders: vec![Option::None; room_count + 3], cups };
+
+    Ok((board, layout))
+}
+
+fn main() {
+
+    let input = std::fs::read_to_string("input.txt").unwrap();
+    let (board, layout) = parse_board(&input).unwrap_or_else(|err| {
+        eprintln!("failed to parse input.txt: {}", err);
+        std::process::exit(1);
     });
 
-    let room = Room { placeholders: [Option::None; 7], cups: cups.try_into().unwrap()};
+    let (cost, moves) = simulate_ordering(&board, &layout);
+    println!("Simulate result {}", cost);
 
-    println!("Simulate result {}", simulate_ordering(&room));
+    if std::env::args().any(|arg| arg == "--animate") {
+        animate_solution(&board, &layout, &moves);
+    }
 }